@@ -17,8 +17,10 @@
 
 use std::fmt::Debug;
 use std::mem::size_of;
+use std::time::Duration;
 
 use async_trait::async_trait;
+use chrono::DateTime;
 use chrono::Utc;
 
 use crate::Buffer;
@@ -59,12 +61,66 @@ pub trait Adapter: Send + Sync + Debug + Unpin + 'static {
     /// Set a value into adapter.
     fn blocking_set(&self, path: &str, value: Value) -> Result<()>;
 
+    /// Set a value into adapter with an expiration.
+    ///
+    /// Adapters that natively support expiry (e.g. moka, dashmap with a
+    /// reaper) should store `ttl` directly; the default implementation
+    /// simply stamps `value` with `Value::with_ttl` and falls back to
+    /// `set`, relying on callers using [`Adapter::get_with_ttl_check`] to
+    /// treat an expired entry as absent.
+    async fn set_with_ttl(&self, path: &str, value: Value, ttl: Duration) -> Result<()> {
+        self.set(path, value.with_ttl(ttl)).await
+    }
+
+    /// Set a value into adapter with an expiration, in a blocking way.
+    fn blocking_set_with_ttl(&self, path: &str, value: Value, ttl: Duration) -> Result<()> {
+        self.blocking_set(path, value.with_ttl(ttl))
+    }
+
     /// Delete a value from adapter.
     async fn delete(&self, path: &str) -> Result<()>;
 
     /// Delete a value from adapter.
     fn blocking_delete(&self, path: &str) -> Result<()>;
 
+    /// Get a value from adapter, treating an expired value as absent.
+    ///
+    /// This layers TTL enforcement on top of [`Adapter::get`]: callers that
+    /// want expired entries to read back as absent (and lazily cleaned up)
+    /// should call this instead. `self.get`/`self.delete` dispatch back to
+    /// whichever type implements `Adapter` (e.g. a wrapper that tracks its
+    /// own state on `delete`), so wrappers don't need to override this to
+    /// stay consistent.
+    async fn get_with_ttl_check(&self, path: &str) -> Result<Option<Value>> {
+        let Some(value) = self.get(path).await? else {
+            return Ok(None);
+        };
+
+        if value.is_expired() {
+            // Best-effort lazy cleanup; a failure to delete doesn't change
+            // the fact that the entry must be treated as absent.
+            let _ = self.delete(path).await;
+            return Ok(None);
+        }
+
+        Ok(Some(value))
+    }
+
+    /// Get a value from adapter, treating an expired value as absent, in a
+    /// blocking way.
+    fn blocking_get_with_ttl_check(&self, path: &str) -> Result<Option<Value>> {
+        let Some(value) = self.blocking_get(path)? else {
+            return Ok(None);
+        };
+
+        if value.is_expired() {
+            let _ = self.blocking_delete(path);
+            return Ok(None);
+        }
+
+        Ok(Some(value))
+    }
+
     /// Scan a key prefix to get all keys that start with this key.
     async fn scan(&self, path: &str) -> Result<Vec<String>> {
         let _ = path;
@@ -87,6 +143,104 @@ pub trait Adapter: Send + Sync + Debug + Unpin + 'static {
         )
         .with_operation("typed_kv::Adapter::blocking_scan"))
     }
+
+    /// Get a batch of values from adapter in a single round trip.
+    ///
+    /// The default implementation loops over [`Adapter::get`], so every
+    /// adapter keeps working without implementing this; adapters whose
+    /// backend supports a native multi-get should override it.
+    async fn batch_get(&self, paths: &[String]) -> Result<Vec<Option<Value>>> {
+        let mut values = Vec::with_capacity(paths.len());
+        for path in paths {
+            values.push(self.get(path).await?);
+        }
+        Ok(values)
+    }
+
+    /// Get a batch of values from adapter in a single round trip, in a
+    /// blocking way.
+    fn blocking_batch_get(&self, paths: &[String]) -> Result<Vec<Option<Value>>> {
+        let mut values = Vec::with_capacity(paths.len());
+        for path in paths {
+            values.push(self.blocking_get(path)?);
+        }
+        Ok(values)
+    }
+
+    /// Set a batch of values into adapter in a single round trip.
+    ///
+    /// The default implementation loops over [`Adapter::set`].
+    async fn batch_set(&self, entries: Vec<(String, Value)>) -> Result<()> {
+        for (path, value) in entries {
+            self.set(&path, value).await?;
+        }
+        Ok(())
+    }
+
+    /// Set a batch of values into adapter in a single round trip, in a
+    /// blocking way.
+    fn blocking_batch_set(&self, entries: Vec<(String, Value)>) -> Result<()> {
+        for (path, value) in entries {
+            self.blocking_set(&path, value)?;
+        }
+        Ok(())
+    }
+
+    /// Delete a batch of values from adapter in a single round trip.
+    ///
+    /// The default implementation loops over [`Adapter::delete`].
+    async fn batch_delete(&self, paths: &[String]) -> Result<()> {
+        for path in paths {
+            self.delete(path).await?;
+        }
+        Ok(())
+    }
+
+    /// Delete a batch of values from adapter in a single round trip, in a
+    /// blocking way.
+    fn blocking_batch_delete(&self, paths: &[String]) -> Result<()> {
+        for path in paths {
+            self.blocking_delete(path)?;
+        }
+        Ok(())
+    }
+
+    /// Scan a half-open key range `[start, end)`, optionally capped at
+    /// `limit` keys.
+    ///
+    /// `start` of `None` means unbounded from the beginning, `end` of `None`
+    /// means unbounded to the end.
+    async fn scan_range(
+        &self,
+        start: Option<&str>,
+        end: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<Vec<String>> {
+        let _ = (start, end, limit);
+
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "typed_kv adapter doesn't support this operation",
+        )
+        .with_operation("typed_kv::Adapter::scan_range"))
+    }
+
+    /// Scan a half-open key range `[start, end)`, optionally capped at
+    /// `limit` keys, in a blocking way.
+    fn blocking_scan_range(
+        &self,
+        start: Option<&str>,
+        end: Option<&str>,
+        limit: Option<usize>,
+    ) -> Result<Vec<String>> {
+        let _ = (start, end, limit);
+
+        Err(Error::new(
+            ErrorKind::Unsupported,
+            "typed_kv adapter doesn't support this operation",
+        )
+        .with_operation("typed_kv::Adapter::blocking_scan_range"))
+    }
 }
 
 /// Value is the typed value stored in adapter.
@@ -98,6 +252,8 @@ pub struct Value {
     pub metadata: Metadata,
     /// The corresponding content of this value.
     pub value: Buffer,
+    /// The time at which this value should be treated as expired, if any.
+    pub expire_at: Option<DateTime<Utc>>,
 }
 
 impl Value {
@@ -108,12 +264,33 @@ impl Value {
                 .with_content_length(0)
                 .with_last_modified(Utc::now()),
             value: Buffer::new(),
+            expire_at: None,
         }
     }
 
     /// Size returns the in-memory size of Value.
     pub fn size(&self) -> usize {
-        size_of::<Metadata>() + self.value.len()
+        size_of::<Metadata>() + self.value.len() + size_of::<Option<DateTime<Utc>>>()
+    }
+
+    /// Set the expiration time of this value.
+    pub fn with_expire_at(mut self, expire_at: DateTime<Utc>) -> Self {
+        self.expire_at = Some(expire_at);
+        self
+    }
+
+    /// Set the expiration of this value as a TTL relative to now.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.expire_at = chrono::Duration::from_std(ttl)
+            .ok()
+            .map(|ttl| Utc::now() + ttl);
+        self
+    }
+
+    /// Returns `true` if this value has an expiration that has passed.
+    pub fn is_expired(&self) -> bool {
+        self.expire_at
+            .is_some_and(|expire_at| expire_at <= Utc::now())
     }
 }
 
@@ -129,6 +306,12 @@ pub struct Capability {
     pub delete: bool,
     /// If typed_kv operator supports scan natively.
     pub scan: bool,
+    /// If typed_kv operator supports expiring entries (TTL) natively.
+    pub ttl: bool,
+    /// If typed_kv operator supports batch get/set/delete natively.
+    pub batch: bool,
+    /// If typed_kv operator supports range scan natively.
+    pub scan_range: bool,
 }
 
 impl Debug for Capability {
@@ -147,6 +330,15 @@ impl Debug for Capability {
         if self.scan {
             s.push("Scan");
         }
+        if self.ttl {
+            s.push("Ttl");
+        }
+        if self.batch {
+            s.push("Batch");
+        }
+        if self.scan_range {
+            s.push("ScanRange");
+        }
 
         write!(f, "{{ {} }}", s.join(" | "))
     }
@@ -184,3 +376,165 @@ impl Info {
         self.capabilities
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct MemoryAdapter {
+        data: Mutex<HashMap<String, Value>>,
+    }
+
+    impl MemoryAdapter {
+        fn new() -> Self {
+            Self {
+                data: Mutex::new(HashMap::new()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Adapter for MemoryAdapter {
+        fn info(&self) -> Info {
+            Info::new(Scheme::Memory, "test", Capability::default())
+        }
+
+        async fn get(&self, path: &str) -> Result<Option<Value>> {
+            self.blocking_get(path)
+        }
+
+        fn blocking_get(&self, path: &str) -> Result<Option<Value>> {
+            Ok(self.data.lock().unwrap().get(path).cloned())
+        }
+
+        async fn set(&self, path: &str, value: Value) -> Result<()> {
+            self.blocking_set(path, value)
+        }
+
+        fn blocking_set(&self, path: &str, value: Value) -> Result<()> {
+            self.data.lock().unwrap().insert(path.to_string(), value);
+            Ok(())
+        }
+
+        async fn delete(&self, path: &str) -> Result<()> {
+            self.blocking_delete(path)
+        }
+
+        fn blocking_delete(&self, path: &str) -> Result<()> {
+            self.data.lock().unwrap().remove(path);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_with_ttl_check_expires_value() {
+        let adapter = MemoryAdapter::new();
+
+        let expired = Value::new_dir().with_expire_at(Utc::now() - chrono::Duration::seconds(1));
+        adapter.set("foo", expired).await.unwrap();
+
+        assert!(adapter.get_with_ttl_check("foo").await.unwrap().is_none());
+        // The expired entry must also have been lazily deleted.
+        assert!(adapter.data.lock().unwrap().get("foo").is_none());
+    }
+
+    #[test]
+    fn test_blocking_get_with_ttl_check_expires_value() {
+        let adapter = MemoryAdapter::new();
+
+        let expired = Value::new_dir().with_expire_at(Utc::now() - chrono::Duration::seconds(1));
+        adapter.blocking_set("foo", expired).unwrap();
+
+        assert!(adapter
+            .blocking_get_with_ttl_check("foo")
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_with_ttl_check_returns_unexpired_value() {
+        let adapter = MemoryAdapter::new();
+
+        let fresh = Value::new_dir().with_ttl(Duration::from_secs(60));
+        adapter.set("foo", fresh).await.unwrap();
+
+        assert!(adapter.get_with_ttl_check("foo").await.unwrap().is_some());
+    }
+
+    #[test]
+    fn test_value_with_ttl_sets_expire_at_in_the_future() {
+        let value = Value::new_dir().with_ttl(Duration::from_secs(60));
+
+        assert!(value.expire_at.unwrap() > Utc::now());
+        assert!(!value.is_expired());
+    }
+
+    #[test]
+    fn test_value_size_accounts_for_expire_at() {
+        let without_ttl = Value::new_dir();
+        let with_ttl = Value::new_dir().with_ttl(Duration::from_secs(60));
+
+        // The `expire_at` field occupies the same bytes whether it's set or
+        // not, so both values report the same size, which includes it.
+        assert_eq!(
+            with_ttl.size(),
+            size_of::<Metadata>() + size_of::<Option<DateTime<Utc>>>()
+        );
+        assert_eq!(with_ttl.size(), without_ttl.size());
+    }
+
+    #[tokio::test]
+    async fn test_batch_set_get_delete_default_impls_loop_over_single_key_ops() {
+        let adapter = MemoryAdapter::new();
+
+        adapter
+            .batch_set(vec![
+                ("a".to_string(), Value::new_dir()),
+                ("b".to_string(), Value::new_dir()),
+            ])
+            .await
+            .unwrap();
+
+        let paths = vec!["a".to_string(), "b".to_string(), "missing".to_string()];
+        let values = adapter.batch_get(&paths).await.unwrap();
+        assert!(values[0].is_some());
+        assert!(values[1].is_some());
+        assert!(values[2].is_none());
+
+        adapter
+            .batch_delete(&["a".to_string(), "b".to_string()])
+            .await
+            .unwrap();
+        assert!(adapter.get("a").await.unwrap().is_none());
+        assert!(adapter.get("b").await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_blocking_batch_set_get_delete_default_impls_loop_over_single_key_ops() {
+        let adapter = MemoryAdapter::new();
+
+        adapter
+            .blocking_batch_set(vec![("a".to_string(), Value::new_dir())])
+            .unwrap();
+        let values = adapter
+            .blocking_batch_get(&["a".to_string(), "missing".to_string()])
+            .unwrap();
+        assert!(values[0].is_some());
+        assert!(values[1].is_none());
+
+        adapter.blocking_batch_delete(&["a".to_string()]).unwrap();
+        assert!(adapter.blocking_get("a").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_scan_range_default_impl_is_unsupported() {
+        let adapter = MemoryAdapter::new();
+
+        let err = adapter.scan_range(None, None, None).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unsupported);
+    }
+}
@@ -0,0 +1,452 @@
+// Licensed to the Apache Software Foundation (ASF) under one
+// or more contributor license agreements.  See the NOTICE file
+// distributed with this work for additional information
+// regarding copyright ownership.  The ASF licenses this file
+// to you under the Apache License, Version 2.0 (the
+// "License"); you may not use this file except in compliance
+// with the License.  You may obtain a copy of the License at
+//
+//   http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing,
+// software distributed under the License is distributed on an
+// "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied.  See the License for the
+// specific language governing permissions and limitations
+// under the License.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use super::Adapter;
+use super::Info;
+use super::Value;
+use crate::Error;
+use crate::ErrorKind;
+use crate::Result;
+
+/// LruAdapter wraps a [`typed_kv::Adapter`](super::Adapter) and enforces a
+/// configurable total byte budget over it, evicting the least-recently-used
+/// entries as needed.
+///
+/// The inner adapter remains the source of truth for stored values; this
+/// wrapper only tracks, per key, the [`Value::size`] it was last written
+/// with and an intrusive recency list, so it can decide what to evict. This
+/// turns any `typed_kv::Adapter` into a drop-in memory-capped cache.
+pub struct LruAdapter<A: Adapter> {
+    inner: A,
+    capacity: usize,
+    state: Mutex<LruState>,
+}
+
+impl<A: Adapter> Debug for LruAdapter<A> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LruAdapter")
+            .field("inner", &self.inner)
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+impl<A: Adapter> LruAdapter<A> {
+    /// Create a new LruAdapter that caps `inner` at `capacity` total bytes,
+    /// as accounted by [`Value::size`].
+    pub fn new(inner: A, capacity: usize) -> Self {
+        Self {
+            inner,
+            capacity,
+            state: Mutex::new(LruState::new()),
+        }
+    }
+
+    fn reject_if_oversized(&self, path: &str, size: usize) -> Result<()> {
+        if size > self.capacity {
+            return Err(Error::new(
+                ErrorKind::Unexpected,
+                "value is larger than the LRU cache's total capacity",
+            )
+            .with_operation("typed_kv::LruAdapter::set")
+            .with_context("path", path));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<A: Adapter> Adapter for LruAdapter<A> {
+    fn info(&self) -> Info {
+        self.inner.info()
+    }
+
+    // Note: we intentionally don't override `get_with_ttl_check` here. Its
+    // default body calls `self.get`/`self.delete`, which dispatch back to
+    // `LruAdapter`'s own overrides below, so an expired entry is removed
+    // from this wrapper's recency state too (not just the inner adapter's).
+    async fn get(&self, path: &str) -> Result<Option<Value>> {
+        let value = self.inner.get(path).await?;
+        if let Some(value) = &value {
+            self.state.lock().unwrap().touch(path, value.size());
+        }
+        Ok(value)
+    }
+
+    fn blocking_get(&self, path: &str) -> Result<Option<Value>> {
+        let value = self.inner.blocking_get(path)?;
+        if let Some(value) = &value {
+            self.state.lock().unwrap().touch(path, value.size());
+        }
+        Ok(value)
+    }
+
+    async fn set(&self, path: &str, value: Value) -> Result<()> {
+        let size = value.size();
+        self.reject_if_oversized(path, size)?;
+
+        self.inner.set(path, value).await?;
+
+        let evicted = {
+            let mut state = self.state.lock().unwrap();
+            state.upsert(path, size);
+            state.evict_until(self.capacity)
+        };
+        for key in evicted {
+            // The write itself already succeeded, so a failure to evict an
+            // unrelated key from the inner adapter shouldn't fail this call.
+            // The key stays untracked by the LRU state but self-heals on its
+            // next `get`/`set` (same best-effort semantics as lazy TTL
+            // deletes).
+            let _ = self.inner.delete(&key).await;
+        }
+
+        Ok(())
+    }
+
+    fn blocking_set(&self, path: &str, value: Value) -> Result<()> {
+        let size = value.size();
+        self.reject_if_oversized(path, size)?;
+
+        self.inner.blocking_set(path, value)?;
+
+        let evicted = {
+            let mut state = self.state.lock().unwrap();
+            state.upsert(path, size);
+            state.evict_until(self.capacity)
+        };
+        for key in evicted {
+            let _ = self.inner.blocking_delete(&key);
+        }
+
+        Ok(())
+    }
+
+    async fn delete(&self, path: &str) -> Result<()> {
+        self.inner.delete(path).await?;
+        self.state.lock().unwrap().remove(path);
+        Ok(())
+    }
+
+    fn blocking_delete(&self, path: &str) -> Result<()> {
+        self.inner.blocking_delete(path)?;
+        self.state.lock().unwrap().remove(path);
+        Ok(())
+    }
+
+    async fn scan(&self, path: &str) -> Result<Vec<String>> {
+        self.inner.scan(path).await
+    }
+
+    fn blocking_scan(&self, path: &str) -> Result<Vec<String>> {
+        self.inner.blocking_scan(path)
+    }
+}
+
+/// LruState tracks, for every key currently accounted for, its size and its
+/// position in an intrusive doubly-linked recency list (`head` is the
+/// most-recently-used key, `tail` is the least-recently-used).
+struct LruState {
+    nodes: HashMap<String, LruNode>,
+    head: Option<String>,
+    tail: Option<String>,
+    current_bytes: usize,
+}
+
+struct LruNode {
+    size: usize,
+    prev: Option<String>,
+    next: Option<String>,
+}
+
+impl LruState {
+    fn new() -> Self {
+        Self {
+            nodes: HashMap::new(),
+            head: None,
+            tail: None,
+            current_bytes: 0,
+        }
+    }
+
+    /// Unlink `key` from the recency list without removing it from `nodes`.
+    fn detach(&mut self, key: &str) {
+        let (prev, next) = {
+            let node = self.nodes.get(key).expect("key must be tracked");
+            (node.prev.clone(), node.next.clone())
+        };
+
+        match &prev {
+            Some(prev_key) => self.nodes.get_mut(prev_key).unwrap().next = next.clone(),
+            None => self.head = next.clone(),
+        }
+        match &next {
+            Some(next_key) => self.nodes.get_mut(next_key).unwrap().prev = prev.clone(),
+            None => self.tail = prev.clone(),
+        }
+    }
+
+    /// Link `key` (already present in `nodes`) as the new most-recently-used
+    /// entry.
+    fn attach_at_head(&mut self, key: String) {
+        let old_head = self.head.replace(key.clone());
+        if let Some(old_head_key) = &old_head {
+            self.nodes.get_mut(old_head_key).unwrap().prev = Some(key.clone());
+        }
+        if self.tail.is_none() {
+            self.tail = Some(key.clone());
+        }
+
+        let node = self.nodes.get_mut(&key).expect("key must be tracked");
+        node.prev = None;
+        node.next = old_head;
+    }
+
+    /// Track `key` at `size`, or update its size if already tracked, moving
+    /// it to the most-recently-used position either way.
+    fn upsert(&mut self, key: &str, size: usize) {
+        if let Some(existing) = self.nodes.get(key) {
+            self.current_bytes -= existing.size;
+            self.detach(key);
+        }
+
+        self.current_bytes += size;
+        self.nodes.insert(
+            key.to_string(),
+            LruNode {
+                size,
+                prev: None,
+                next: None,
+            },
+        );
+        self.attach_at_head(key.to_string());
+    }
+
+    /// Move `key` to the most-recently-used position. If it isn't tracked
+    /// yet (e.g. it was written directly through the inner adapter, bypassing
+    /// the wrapper), start tracking it at `size` so future evictions account
+    /// for it.
+    fn touch(&mut self, key: &str, size: usize) {
+        if self.nodes.contains_key(key) {
+            self.detach(key);
+            self.attach_at_head(key.to_string());
+        } else {
+            self.current_bytes += size;
+            self.nodes.insert(
+                key.to_string(),
+                LruNode {
+                    size,
+                    prev: None,
+                    next: None,
+                },
+            );
+            self.attach_at_head(key.to_string());
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        if self.nodes.contains_key(key) {
+            self.current_bytes -= self.nodes[key].size;
+            self.detach(key);
+            self.nodes.remove(key);
+        }
+    }
+
+    /// Evict least-recently-used entries until `current_bytes <= capacity`,
+    /// returning the evicted keys so the caller can delete them from the
+    /// inner adapter outside the lock.
+    fn evict_until(&mut self, capacity: usize) -> Vec<String> {
+        let mut evicted = Vec::new();
+
+        while self.current_bytes > capacity {
+            let Some(tail_key) = self.tail.clone() else {
+                break;
+            };
+
+            self.current_bytes -= self.nodes[&tail_key].size;
+            self.detach(&tail_key);
+            self.nodes.remove(&tail_key);
+            evicted.push(tail_key);
+        }
+
+        evicted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+
+    use super::super::Capability;
+    use super::*;
+    use crate::Scheme;
+
+    /// A bare-bones in-memory `Adapter` for exercising `LruAdapter` in
+    /// isolation. `fail_delete` lets tests simulate an inner adapter whose
+    /// `delete` fails, without affecting `set`/`get`.
+    #[derive(Debug, Default)]
+    struct MemoryAdapter {
+        data: Mutex<HashMap<String, Value>>,
+        fail_delete: Mutex<bool>,
+    }
+
+    impl MemoryAdapter {
+        fn new() -> Self {
+            Self::default()
+        }
+
+        fn set_fail_delete(&self, fail: bool) {
+            *self.fail_delete.lock().unwrap() = fail;
+        }
+    }
+
+    #[async_trait]
+    impl Adapter for MemoryAdapter {
+        fn info(&self) -> Info {
+            Info::new(Scheme::Memory, "memory", Capability::default())
+        }
+
+        async fn get(&self, path: &str) -> Result<Option<Value>> {
+            Ok(self.data.lock().unwrap().get(path).cloned())
+        }
+
+        fn blocking_get(&self, path: &str) -> Result<Option<Value>> {
+            Ok(self.data.lock().unwrap().get(path).cloned())
+        }
+
+        async fn set(&self, path: &str, value: Value) -> Result<()> {
+            self.data.lock().unwrap().insert(path.to_string(), value);
+            Ok(())
+        }
+
+        fn blocking_set(&self, path: &str, value: Value) -> Result<()> {
+            self.data.lock().unwrap().insert(path.to_string(), value);
+            Ok(())
+        }
+
+        async fn delete(&self, path: &str) -> Result<()> {
+            if *self.fail_delete.lock().unwrap() {
+                return Err(Error::new(
+                    ErrorKind::Unexpected,
+                    "simulated delete failure",
+                ));
+            }
+            self.data.lock().unwrap().remove(path);
+            Ok(())
+        }
+
+        fn blocking_delete(&self, path: &str) -> Result<()> {
+            if *self.fail_delete.lock().unwrap() {
+                return Err(Error::new(
+                    ErrorKind::Unexpected,
+                    "simulated delete failure",
+                ));
+            }
+            self.data.lock().unwrap().remove(path);
+            Ok(())
+        }
+    }
+
+    /// All entries built via `Value::new_dir()` have the same fixed size, so
+    /// tests size `capacity` relative to it instead of hard-coding a byte
+    /// count that depends on `Metadata`'s in-memory layout.
+    fn entry_size() -> usize {
+        Value::new_dir().size()
+    }
+
+    #[tokio::test]
+    async fn test_set_rejects_value_larger_than_capacity() {
+        let lru = LruAdapter::new(MemoryAdapter::new(), entry_size() - 1);
+
+        let err = lru.set("a", Value::new_dir()).await.unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unexpected);
+    }
+
+    #[tokio::test]
+    async fn test_set_evicts_least_recently_used_entry_over_capacity() {
+        let lru = LruAdapter::new(MemoryAdapter::new(), entry_size() * 2);
+
+        lru.set("a", Value::new_dir()).await.unwrap();
+        lru.set("b", Value::new_dir()).await.unwrap();
+        lru.set("c", Value::new_dir()).await.unwrap();
+
+        // "a" was the least-recently-used key and should have been evicted
+        // to keep total bytes within capacity.
+        assert!(lru.get("a").await.unwrap().is_none());
+        assert!(lru.get("b").await.unwrap().is_some());
+        assert!(lru.get("c").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_get_promotes_entry_to_most_recently_used() {
+        let lru = LruAdapter::new(MemoryAdapter::new(), entry_size() * 2);
+
+        lru.set("a", Value::new_dir()).await.unwrap();
+        lru.set("b", Value::new_dir()).await.unwrap();
+        // Touch "a" so it becomes more recently used than "b".
+        lru.get("a").await.unwrap();
+        lru.set("c", Value::new_dir()).await.unwrap();
+
+        // "b" is now the least-recently-used key and should be evicted
+        // instead of "a".
+        assert!(lru.get("a").await.unwrap().is_some());
+        assert!(lru.get("b").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_set_succeeds_even_if_eviction_delete_fails() {
+        let lru = LruAdapter::new(MemoryAdapter::new(), entry_size() * 2);
+
+        lru.set("a", Value::new_dir()).await.unwrap();
+        lru.set("b", Value::new_dir()).await.unwrap();
+
+        lru.inner.set_fail_delete(true);
+        // "c" forces "a" to be evicted; the inner delete will fail, but the
+        // write of "c" itself must still be reported as successful.
+        lru.set("c", Value::new_dir()).await.unwrap();
+
+        assert!(lru.get("c").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_get_with_ttl_check_evicts_expired_entry_from_lru_state() {
+        let lru = LruAdapter::new(MemoryAdapter::new(), entry_size() * 2);
+
+        let expired =
+            Value::new_dir().with_expire_at(chrono::Utc::now() - chrono::Duration::seconds(1));
+        lru.set("a", expired).await.unwrap();
+        assert_eq!(lru.state.lock().unwrap().current_bytes, entry_size());
+
+        assert!(lru.get_with_ttl_check("a").await.unwrap().is_none());
+
+        // The lazy expiry delete must have gone through `LruAdapter::delete`
+        // (not just the inner adapter's), so its own byte accounting is
+        // updated too.
+        assert_eq!(lru.state.lock().unwrap().current_bytes, 0);
+    }
+}
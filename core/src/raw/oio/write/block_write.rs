@@ -15,14 +15,19 @@
 // specific language governing permissions and limitations
 // under the License.
 
+use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::sync::Mutex;
 use std::task::Context;
 use std::task::Poll;
+use std::time::Duration;
+use std::time::Instant;
 
 use futures::Future;
 use futures::FutureExt;
 use futures::StreamExt;
+use sha2::Digest;
 use uuid::Uuid;
 
 use crate::raw::*;
@@ -81,18 +86,105 @@ pub trait BlockWrite: Send + Sync + Unpin + 'static {
         body: Buffer,
     ) -> impl Future<Output = Result<()>> + MaybeSend;
 
+    /// write_block_with_checksum is like [`BlockWrite::write_block`] but also
+    /// carries the precomputed [`Checksum`] of `body`, for services that can
+    /// validate a block's content hash on the wire (e.g. S3 `Content-MD5`,
+    /// Azure block MD5).
+    ///
+    /// The default implementation ignores the checksum and delegates to
+    /// `write_block`, so services that don't verify block checksums don't
+    /// need to do anything.
+    fn write_block_with_checksum(
+        &self,
+        block_id: Uuid,
+        size: u64,
+        checksum: Checksum,
+        body: Buffer,
+    ) -> impl Future<Output = Result<()>> + MaybeSend {
+        let _ = checksum;
+        self.write_block(block_id, size, body)
+    }
+
     /// complete_block will complete the block upload to build the final
     /// file.
     fn complete_block(&self, block_ids: Vec<Uuid>) -> impl Future<Output = Result<()>> + MaybeSend;
 
+    /// complete_block_with_checksums is like [`BlockWrite::complete_block`]
+    /// but also carries the ordered `(Uuid, Checksum)` pairs, for services
+    /// that validate the full block manifest against per-block checksums.
+    ///
+    /// The default implementation ignores the checksums and delegates to
+    /// `complete_block`.
+    fn complete_block_with_checksums(
+        &self,
+        blocks: Vec<(Uuid, Checksum)>,
+    ) -> impl Future<Output = Result<()>> + MaybeSend {
+        let block_ids = blocks.into_iter().map(|(id, _)| id).collect();
+        self.complete_block(block_ids)
+    }
+
     /// abort_block will cancel the block upload and purge all data.
     fn abort_block(&self, block_ids: Vec<Uuid>) -> impl Future<Output = Result<()>> + MaybeSend;
 }
 
+/// ChecksumAlgorithm selects how [`BlockWriter`] computes a digest for each
+/// block when checksum support is enabled via [`BlockWriter::with_checksum`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    /// CRC32C, e.g. used by S3's additional checksum headers.
+    Crc32c,
+    /// MD5, e.g. used by S3 `Content-MD5` and Azure block MD5.
+    Md5,
+    /// SHA-256.
+    Sha256,
+}
+
+/// Checksum is the digest of a single block, computed by [`BlockWriter`]
+/// according to the configured [`ChecksumAlgorithm`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksum {
+    /// The algorithm used to compute `digest`.
+    pub algorithm: ChecksumAlgorithm,
+    /// The raw digest bytes.
+    pub digest: Vec<u8>,
+}
+
+impl Checksum {
+    /// Compute the checksum of `buffer` incrementally over its chunks.
+    fn compute(algorithm: ChecksumAlgorithm, buffer: &Buffer) -> Self {
+        let digest = match algorithm {
+            ChecksumAlgorithm::Crc32c => {
+                let mut crc = 0u32;
+                for chunk in buffer.clone() {
+                    crc = crc32c::crc32c_append(crc, &chunk);
+                }
+                crc.to_be_bytes().to_vec()
+            }
+            ChecksumAlgorithm::Md5 => {
+                let mut hasher = md5::Md5::new();
+                for chunk in buffer.clone() {
+                    hasher.update(&chunk);
+                }
+                hasher.finalize().to_vec()
+            }
+            ChecksumAlgorithm::Sha256 => {
+                let mut hasher = sha2::Sha256::new();
+                for chunk in buffer.clone() {
+                    hasher.update(&chunk);
+                }
+                hasher.finalize().to_vec()
+            }
+        };
+
+        Checksum { algorithm, digest }
+    }
+}
+
 /// WriteBlockResult is the result returned by [`WriteBlockFuture`].
 ///
 /// The error part will carries input `(block_id, bytes, err)` so caller can retry them.
-type WriteBlockResult = Result<Uuid, (Uuid, Buffer, Error)>;
+/// The ok part carries the checksum of the block when checksum support is enabled.
+type WriteBlockResult = Result<(Uuid, Option<Checksum>), (Uuid, Buffer, Error)>;
 
 struct WriteBlockFuture(BoxedStaticFuture<WriteBlockResult>);
 
@@ -114,28 +206,169 @@ impl Future for WriteBlockFuture {
 }
 
 impl WriteBlockFuture {
-    pub fn new<W: BlockWrite>(w: Arc<W>, block_id: Uuid, bytes: Buffer) -> Self {
+    /// Create a new WriteBlockFuture that sleeps for `delay` before calling
+    /// `write_block` (or `write_block_with_checksum` when `checksum` is
+    /// configured). Used to back off a retried block without blocking the
+    /// other in-flight futures that share the same `ConcurrentFutures` queue.
+    pub fn new_with_delay<W: BlockWrite>(
+        w: Arc<W>,
+        block_id: Uuid,
+        bytes: Buffer,
+        delay: Duration,
+        checksum: Option<ChecksumAlgorithm>,
+    ) -> Self {
         let fut = async move {
-            w.write_block(block_id, bytes.len() as u64, bytes.clone())
-                .await
+            if !delay.is_zero() {
+                tokio::time::sleep(delay).await;
+            }
+
+            let checksum = checksum.map(|algorithm| Checksum::compute(algorithm, &bytes));
+
+            let result = match checksum.clone() {
+                Some(checksum) => {
+                    w.write_block_with_checksum(
+                        block_id,
+                        bytes.len() as u64,
+                        checksum,
+                        bytes.clone(),
+                    )
+                    .await
+                }
+                None => {
+                    w.write_block(block_id, bytes.len() as u64, bytes.clone())
+                        .await
+                }
+            };
+
+            result
                 // Return bytes while we got an error to allow retry.
                 .map_err(|err| (block_id, bytes, err))
-                // Return the successful block id.
-                .map(|_| block_id)
+                // Return the successful block id and its checksum.
+                .map(|_| (block_id, checksum))
         };
 
         WriteBlockFuture(Box::pin(fut))
     }
 }
 
+/// RetryPolicy controls how [`BlockWriter`] retries a failed block before
+/// giving up and surfacing the error to the caller.
+struct RetryPolicy {
+    max_retries: usize,
+    min_delay: Duration,
+    max_delay: Duration,
+    factor: f64,
+}
+
+impl RetryPolicy {
+    /// Compute the backoff delay for the given zero-based attempt number.
+    fn delay_for(&self, attempt: usize) -> Duration {
+        let backoff = self.min_delay.as_secs_f64() * self.factor.powi(attempt as i32);
+        let backoff = backoff.min(self.max_delay.as_secs_f64());
+
+        // Add up to 10% jitter so that blocks retried at the same attempt
+        // count don't all wake up and hammer the service at once.
+        let jitter = backoff * 0.1 * jitter_fraction();
+
+        Duration::from_secs_f64(backoff + jitter)
+    }
+}
+
+/// A cheap, dependency-free source of jitter in `[0, 1)`. It doesn't need to
+/// be cryptographically random, only spread out across concurrent callers.
+fn jitter_fraction() -> f64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.subsec_nanos())
+        .unwrap_or(0);
+
+    (nanos % 1_000) as f64 / 1_000.0
+}
+
+/// RateLimiter implements a classic token bucket that is shared across all
+/// in-flight blocks of a [`BlockWriter`].
+///
+/// Tokens are refilled lazily (on every [`RateLimiter::acquire`] call) based
+/// on the elapsed time since the last refill, which avoids the need for a
+/// background task.
+struct RateLimiter {
+    /// Burst capacity, in bytes.
+    capacity: f64,
+    /// Refill rate, in bytes per second.
+    rate: f64,
+
+    state: Mutex<RateLimiterState>,
+}
+
+struct RateLimiterState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    fn new(bytes_per_sec: u64, burst: u64) -> Self {
+        Self {
+            capacity: burst as f64,
+            rate: bytes_per_sec as f64,
+            state: Mutex::new(RateLimiterState {
+                tokens: burst as f64,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    /// Block until `size` bytes worth of tokens are available and consume them.
+    ///
+    /// Blocks larger than `capacity` are still debited their full `size`, so
+    /// they don't get a free ride past the configured rate; they just only
+    /// need to wait for the bucket to fill up to `capacity` (its maximum)
+    /// rather than to `size` before being let through.
+    async fn acquire(&self, size: u64) {
+        let size = size as f64;
+        // A zero rate never refills, so there's nothing to wait for: let the
+        // request through immediately rather than computing a wait of +inf.
+        if self.rate <= 0.0 {
+            return;
+        }
+        let billable = size.min(self.capacity);
+
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+
+                let elapsed = state.last_refill.elapsed().as_secs_f64();
+                state.tokens = (state.tokens + self.rate * elapsed).min(self.capacity);
+                state.last_refill = Instant::now();
+
+                if state.tokens >= billable {
+                    state.tokens -= size;
+                    None
+                } else {
+                    Some((billable - state.tokens) / self.rate)
+                }
+            };
+
+            match wait {
+                None => return,
+                Some(wait) => tokio::time::sleep(Duration::from_secs_f64(wait)).await,
+            }
+        }
+    }
+}
+
 /// BlockWriter will implements [`Write`] based on block
 /// uploads.
 pub struct BlockWriter<W: BlockWrite> {
     w: Arc<W>,
 
     block_ids: Vec<Uuid>,
+    checksums: HashMap<Uuid, Checksum>,
     cache: Option<Buffer>,
     futures: ConcurrentFutures<WriteBlockFuture>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    retry: Option<RetryPolicy>,
+    attempts: HashMap<Uuid, usize>,
+    checksum: Option<ChecksumAlgorithm>,
 }
 
 impl<W: BlockWrite> BlockWriter<W> {
@@ -144,17 +377,160 @@ impl<W: BlockWrite> BlockWriter<W> {
         Self {
             w: Arc::new(inner),
             block_ids: Vec::new(),
+            checksums: HashMap::new(),
             cache: None,
             futures: ConcurrentFutures::new(1.max(concurrent)),
+            rate_limiter: None,
+            retry: None,
+            attempts: HashMap::new(),
+            checksum: None,
         }
     }
 
+    /// Compute and carry a [`Checksum`] for every block using `algorithm`.
+    ///
+    /// The checksum is passed to [`BlockWrite::write_block_with_checksum`]
+    /// for each block and, once all blocks have succeeded, the ordered
+    /// `(Uuid, Checksum)` pairs are passed to
+    /// [`BlockWrite::complete_block_with_checksums`] so the backend can
+    /// reject a corrupted upload instead of committing it.
+    pub fn with_checksum(mut self, algorithm: ChecksumAlgorithm) -> Self {
+        self.checksum = Some(algorithm);
+        self
+    }
+
+    /// Configure a retry policy so that transient per-block failures are
+    /// retried internally instead of being surfaced to the caller.
+    ///
+    /// `max_retries` bounds the number of retries per block after its first
+    /// attempt; the delay between attempts grows as
+    /// `min_delay * factor ^ attempt`, capped at `max_delay`, plus up to 10%
+    /// jitter on top of that. Only errors for which [`Error::is_temporary`] returns
+    /// `true` are retried, so permanent errors still fail fast.
+    pub fn with_retry(
+        mut self,
+        max_retries: usize,
+        min_delay: Duration,
+        max_delay: Duration,
+        factor: f64,
+    ) -> Self {
+        self.retry = Some(RetryPolicy {
+            max_retries,
+            min_delay,
+            max_delay,
+            factor,
+        });
+        self
+    }
+
+    /// Configure a byte-rate limit for this writer.
+    ///
+    /// `bytes_per_sec` is the sustained refill rate and `burst` is the token
+    /// bucket's capacity, both in bytes. Every block is gated against the
+    /// limiter before it's dispatched, and the limiter is shared across all
+    /// concurrently in-flight blocks. Without calling this, the writer has
+    /// zero throttling overhead.
+    pub fn with_rate_limit(mut self, bytes_per_sec: u64, burst: u64) -> Self {
+        self.rate_limiter = Some(Arc::new(RateLimiter::new(bytes_per_sec, burst)));
+        self
+    }
+
     fn fill_cache(&mut self, bs: Buffer) -> usize {
         let size = bs.len();
         assert!(self.cache.is_none());
         self.cache = Some(bs);
         size
     }
+
+    async fn throttle(&self, size: u64) {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire(size).await;
+        }
+    }
+
+    /// Dispatch a new block for upload, honoring the configured checksum
+    /// algorithm if any.
+    fn dispatch_block(&mut self, block_id: Uuid, bytes: Buffer) {
+        self.futures.push_back(WriteBlockFuture::new_with_delay(
+            self.w.clone(),
+            block_id,
+            bytes,
+            Duration::ZERO,
+            self.checksum,
+        ));
+    }
+
+    /// Record a successfully uploaded block, along with its checksum if any.
+    fn record_block(&mut self, block_id: Uuid, checksum: Option<Checksum>) {
+        self.block_ids.push(block_id);
+        if let Some(checksum) = checksum {
+            self.checksums.insert(block_id, checksum);
+        }
+        // A block that needed one or more retries before succeeding must not
+        // keep its attempt counter around for the rest of the writer's life.
+        self.attempts.remove(&block_id);
+    }
+
+    /// Handle a failed block: requeue it for retry if a retry policy is
+    /// configured, the error is temporary, and retries remain. Returns the
+    /// error to surface to the caller once retries are exhausted (or retry
+    /// isn't configured / the error isn't retryable), and `None` if the block
+    /// has been requeued.
+    ///
+    /// Either way the block is re-dispatched through [`Self::throttle`]
+    /// first, the same as a first-time dispatch, so retried blocks can't
+    /// bypass the configured rate limit.
+    async fn requeue_or_fail(
+        &mut self,
+        block_id: Uuid,
+        bytes: Buffer,
+        err: Error,
+    ) -> Option<Error> {
+        if let Some(policy) = &self.retry {
+            if err.is_temporary() {
+                let attempt = *self.attempts.get(&block_id).unwrap_or(&0);
+                if attempt < policy.max_retries {
+                    self.attempts.insert(block_id, attempt + 1);
+                    let delay = policy.delay_for(attempt);
+                    self.throttle(bytes.len() as u64).await;
+                    self.futures.push_front(WriteBlockFuture::new_with_delay(
+                        self.w.clone(),
+                        block_id,
+                        bytes,
+                        delay,
+                        self.checksum,
+                    ));
+                    return None;
+                }
+            }
+        }
+
+        self.attempts.remove(&block_id);
+        self.throttle(bytes.len() as u64).await;
+        self.futures.push_front(WriteBlockFuture::new_with_delay(
+            self.w.clone(),
+            block_id,
+            bytes,
+            Duration::ZERO,
+            self.checksum,
+        ));
+        Some(err)
+    }
+
+    /// Build the ordered `(Uuid, Checksum)` pairs for [`BlockWrite::complete_block_with_checksums`].
+    fn checksummed_blocks(&self) -> Vec<(Uuid, Checksum)> {
+        self.block_ids
+            .iter()
+            .map(|id| {
+                let checksum = self
+                    .checksums
+                    .get(id)
+                    .cloned()
+                    .expect("checksum must be recorded for every block when enabled");
+                (*id, checksum)
+            })
+            .collect()
+    }
 }
 
 impl<W> oio::Write for BlockWriter<W>
@@ -171,27 +547,22 @@ where
                 }
 
                 let cache = self.cache.take().expect("pending write must exist");
-                self.futures.push_back(WriteBlockFuture::new(
-                    self.w.clone(),
-                    Uuid::new_v4(),
-                    cache,
-                ));
+                self.throttle(cache.len() as u64).await;
+                self.dispatch_block(Uuid::new_v4(), cache);
 
                 let size = self.fill_cache(bs);
                 return Ok(size);
             } else if let Some(res) = self.futures.next().await {
                 match res {
-                    Ok(block_id) => {
-                        self.block_ids.push(block_id);
+                    Ok((block_id, checksum)) => {
+                        self.record_block(block_id, checksum);
                         continue;
                     }
                     Err((block_id, bytes, err)) => {
-                        self.futures.push_front(WriteBlockFuture::new(
-                            self.w.clone(),
-                            block_id,
-                            bytes,
-                        ));
-                        return Err(err);
+                        if let Some(err) = self.requeue_or_fail(block_id, bytes, err).await {
+                            return Err(err);
+                        }
+                        continue;
                     }
                 }
             }
@@ -216,11 +587,8 @@ where
                 // Push into the queue and continue.
                 // It's safe to take the cache here since we will re-push task for it failed.
                 if let Some(cache) = self.cache.take() {
-                    self.futures.push_back(WriteBlockFuture::new(
-                        self.w.clone(),
-                        Uuid::new_v4(),
-                        cache,
-                    ));
+                    self.throttle(cache.len() as u64).await;
+                    self.dispatch_block(Uuid::new_v4(), cache);
                 }
             }
 
@@ -229,20 +597,26 @@ where
             };
 
             match result {
-                Ok(block_id) => {
-                    self.block_ids.push(block_id);
+                Ok((block_id, checksum)) => {
+                    self.record_block(block_id, checksum);
                     continue;
                 }
                 Err((block_id, bytes, err)) => {
-                    self.futures
-                        .push_front(WriteBlockFuture::new(self.w.clone(), block_id, bytes));
-                    return Err(err);
+                    if let Some(err) = self.requeue_or_fail(block_id, bytes, err).await {
+                        return Err(err);
+                    }
+                    continue;
                 }
             }
         }
 
-        let block_ids = self.block_ids.clone();
-        self.w.complete_block(block_ids).await
+        if self.checksum.is_some() {
+            self.w
+                .complete_block_with_checksums(self.checksummed_blocks())
+                .await
+        } else {
+            self.w.complete_block(self.block_ids.clone()).await
+        }
     }
 
     async fn abort(&mut self) -> Result<()> {
@@ -357,4 +731,100 @@ mod tests {
             "content must be the same"
         );
     }
+
+    #[tokio::test]
+    async fn test_rate_limiter_oversized_block_debits_full_size() {
+        let limiter = RateLimiter::new(1000, 1000);
+
+        limiter.acquire(5000).await;
+
+        let tokens = limiter.state.lock().unwrap().tokens;
+        assert!(
+            tokens <= 0.0,
+            "an oversized block must debit its full size, not just `capacity`, got {tokens} tokens left"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_zero_rate_does_not_panic() {
+        let limiter = RateLimiter::new(0, 1000);
+
+        // Must return promptly instead of computing an infinite wait.
+        limiter.acquire(5000).await;
+    }
+
+    #[test]
+    fn test_checksum_compute_crc32c_is_consistent() {
+        let buffer: Buffer = b"hello world".to_vec().into();
+
+        let a = Checksum::compute(ChecksumAlgorithm::Crc32c, &buffer);
+        let b = Checksum::compute(ChecksumAlgorithm::Crc32c, &buffer);
+
+        assert_eq!(a, b);
+        assert_eq!(a.algorithm, ChecksumAlgorithm::Crc32c);
+        assert_eq!(a.digest.len(), 4);
+    }
+
+    #[test]
+    fn test_checksum_compute_differs_across_algorithms() {
+        let buffer: Buffer = b"hello world".to_vec().into();
+
+        let crc32c = Checksum::compute(ChecksumAlgorithm::Crc32c, &buffer);
+        let md5 = Checksum::compute(ChecksumAlgorithm::Md5, &buffer);
+        let sha256 = Checksum::compute(ChecksumAlgorithm::Sha256, &buffer);
+
+        assert_eq!(md5.digest.len(), 16);
+        assert_eq!(sha256.digest.len(), 32);
+        assert_ne!(crc32c.digest, md5.digest);
+    }
+
+    #[test]
+    fn test_retry_policy_delay_for_is_capped_at_max_delay() {
+        let policy = RetryPolicy {
+            max_retries: 5,
+            min_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(500),
+            factor: 10.0,
+        };
+
+        // With factor 10 a late attempt would blow well past max_delay
+        // without the cap.
+        let delay = policy.delay_for(4);
+        assert!(delay <= Duration::from_millis(550), "got {delay:?}");
+    }
+
+    #[tokio::test]
+    async fn test_record_block_clears_attempt_counter() {
+        let mut w = BlockWriter::new(TestWrite::new(), 8);
+        let block_id = Uuid::new_v4();
+        w.attempts.insert(block_id, 2);
+
+        w.record_block(block_id, None);
+
+        assert!(
+            !w.attempts.contains_key(&block_id),
+            "a successfully recorded block must not leave a stale retry counter behind"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_requeue_or_fail_throttles_the_redispatched_block() {
+        let mut w = BlockWriter::new(TestWrite::new(), 8)
+            .with_retry(3, Duration::from_millis(1), Duration::from_millis(1), 1.0)
+            .with_rate_limit(1000, 1000);
+        let limiter = w.rate_limiter.clone().unwrap();
+
+        let block_id = Uuid::new_v4();
+        let bytes: Buffer = vec![0u8; 600].into();
+        let err = Error::new(ErrorKind::Unexpected, "transient").set_temporary();
+
+        let result = w.requeue_or_fail(block_id, bytes, err).await;
+        assert!(result.is_none(), "a retryable error must be requeued");
+
+        let tokens = limiter.state.lock().unwrap().tokens;
+        assert!(
+            tokens <= 400.0,
+            "retried block must be throttled like a first dispatch, got {tokens} tokens left"
+        );
+    }
 }